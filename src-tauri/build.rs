@@ -0,0 +1,45 @@
+//! Captures git branch, short commit hash, working-tree dirty flag, and a build timestamp
+//! into compile-time env vars consumed by `app_version` in `src/main.rs` -- so a bug report
+//! that quotes the app version can be traced back to the exact commit (and whether it was a
+//! clean checkout) that produced that build, not just the crate version number.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git_output(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+fn main() {
+    let branch =
+        git_output(&["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    let commit =
+        git_output(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    let dirty = git_output(&["status", "--porcelain"])
+        .map(|status| !status.is_empty())
+        .unwrap_or(false);
+    let build_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs().to_string())
+        .unwrap_or_else(|_| "0".to_string());
+
+    println!("cargo:rustc-env=WORKFLOW_GIT_BRANCH={branch}");
+    println!("cargo:rustc-env=WORKFLOW_GIT_COMMIT={commit}");
+    println!("cargo:rustc-env=WORKFLOW_GIT_DIRTY={dirty}");
+    println!("cargo:rustc-env=WORKFLOW_BUILD_TIME={build_time}");
+
+    // Re-run whenever HEAD moves or the tree is staged/unstaged, so a rebuild after switching
+    // branches or committing picks up fresh provenance instead of a stale cached value.
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+}
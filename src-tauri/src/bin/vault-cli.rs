@@ -0,0 +1,292 @@
+//! Headless companion to the Workflow GUI. Links the same `vault` engine
+//! (crypto envelope sealing, the append-only encrypted DB store, and auth
+//! verification) so scripted backups and automation work identically to the
+//! app, without a window or `AppHandle`.
+//!
+//! Usage:
+//!   vault-cli unlock       --data-dir <dir> --password <pw>
+//!   vault-cli list-tables  --data-dir <dir> --password <pw>
+//!   vault-cli export-table --data-dir <dir> --password <pw> --table <id> --out <file.csv>
+//!   vault-cli dump-todos   --data-dir <dir> --password <pw> --out <file.csv>
+//!   vault-cli dump-weekly  --data-dir <dir> --password <pw> --out <file.csv>
+//!   vault-cli apply-backup --data-dir <dir> --password <pw> --file <backup.json>
+
+#[path = "../vault.rs"]
+mod vault;
+
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+struct Args {
+    data_dir: Option<PathBuf>,
+    password: Option<String>,
+    table: Option<String>,
+    out: Option<PathBuf>,
+    file: Option<PathBuf>,
+}
+
+fn parse_args(raw: &[String]) -> Result<Args, String> {
+    let mut args = Args {
+        data_dir: None,
+        password: None,
+        table: None,
+        out: None,
+        file: None,
+    };
+    let mut iter = raw.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {flag}"))?;
+        match flag.as_str() {
+            "--data-dir" => args.data_dir = Some(PathBuf::from(value)),
+            "--password" => args.password = Some(value.clone()),
+            "--table" => args.table = Some(value.clone()),
+            "--out" => args.out = Some(PathBuf::from(value)),
+            "--file" => args.file = Some(PathBuf::from(value)),
+            other => return Err(format!("Unknown flag {other}")),
+        }
+    }
+    Ok(args)
+}
+
+fn require_data_dir(args: &Args) -> Result<&Path, String> {
+    args.data_dir
+        .as_deref()
+        .ok_or_else(|| "Missing required --data-dir <path>".to_string())
+}
+
+fn require_password(args: &Args) -> Result<&str, String> {
+    args.password
+        .as_deref()
+        .ok_or_else(|| "Missing required --password <value>".to_string())
+}
+
+fn unlock(args: &Args) -> Result<(), String> {
+    let root = require_data_dir(args)?;
+    let password = require_password(args)?;
+    if vault::verify_auth_password(root, password)?.ok {
+        println!("ok");
+        Ok(())
+    } else {
+        Err("Invalid password.".to_string())
+    }
+}
+
+fn list_tables(args: &Args) -> Result<(), String> {
+    let root = require_data_dir(args)?;
+    let password = require_password(args)?;
+    if !vault::verify_auth_password(root, password)?.ok {
+        return Err("Invalid password.".to_string());
+    }
+    let db = vault::load_db_value(root, password)?;
+    for table_id in vault::DB_TABLE_ORDER {
+        println!(
+            "{}\t{}\t{}",
+            table_id,
+            vault::table_display_name(table_id),
+            vault::db_table_count(&db, table_id)
+        );
+    }
+    Ok(())
+}
+
+/// Auto-derives CSV columns from the union of keys across all `rows` (first-seen order,
+/// `__rowId` skipped). The GUI's `db_export_csv` fallback differs: it takes its columns
+/// from the first row only.
+fn rows_to_csv_auto(rows: &[serde_json::Value]) -> String {
+    let mut columns: Vec<String> = Vec::new();
+    let mut seen = BTreeSet::new();
+    for row in rows {
+        let Some(obj) = row.as_object() else { continue };
+        for key in obj.keys() {
+            if key == "__rowId" || !seen.insert(key.clone()) {
+                continue;
+            }
+            columns.push(key.clone());
+        }
+    }
+    vault::rows_to_csv(columns.as_slice(), rows)
+}
+
+fn write_csv(out: &Path, csv: &str) -> Result<(), String> {
+    vault::write_text_file(out.to_path_buf(), csv)
+}
+
+fn table_rows(db: &serde_json::Value, table_id: &str) -> Vec<serde_json::Value> {
+    match table_id {
+        "kanban_columns" => db
+            .get("kanban")
+            .and_then(|v| v.get("columns"))
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        "kanban_cards" => db
+            .get("kanban")
+            .and_then(|v| v.get("cards"))
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        "candidate_data" => db
+            .get("kanban")
+            .and_then(|v| v.get("candidates"))
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        "uniform_inventory" => db
+            .get("uniforms")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        "todos" => db
+            .get("todos")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        "weekly_entries" => weekly_rows(db),
+        _ => Vec::new(),
+    }
+}
+
+fn weekly_rows(db: &serde_json::Value) -> Vec<serde_json::Value> {
+    let Some(weekly) = db.get("weekly").and_then(|v| v.as_object()) else {
+        return Vec::new();
+    };
+    let mut rows = Vec::new();
+    for (week_key, week) in weekly {
+        let week_start = week
+            .get("week_start")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| week_key.clone());
+        let week_end = vault::js_like_value_string(week.get("week_end"));
+        let Some(entries) = week.get("entries").and_then(|v| v.as_object()) else {
+            continue;
+        };
+        for (day, entry) in entries {
+            rows.push(json!({
+                "week_start": week_start,
+                "week_end": week_end,
+                "day": day,
+                "start": vault::js_like_value_string(entry.get("start")),
+                "end": vault::js_like_value_string(entry.get("end")),
+                "content": vault::js_like_value_string(entry.get("content")),
+            }));
+        }
+    }
+    rows
+}
+
+fn export_table(args: &Args) -> Result<(), String> {
+    let root = require_data_dir(args)?;
+    let password = require_password(args)?;
+    let table = args
+        .table
+        .as_deref()
+        .ok_or_else(|| "Missing required --table <id>".to_string())?;
+    let out = args
+        .out
+        .as_deref()
+        .ok_or_else(|| "Missing required --out <file.csv>".to_string())?;
+    if !vault::verify_auth_password(root, password)?.ok {
+        return Err("Invalid password.".to_string());
+    }
+    let db = vault::load_db_value(root, password)?;
+    let rows = table_rows(&db, table);
+    write_csv(out, rows_to_csv_auto(rows.as_slice()).as_str())?;
+    println!("wrote {} rows to {}", rows.len(), out.display());
+    Ok(())
+}
+
+fn dump_todos(args: &Args) -> Result<(), String> {
+    let root = require_data_dir(args)?;
+    let password = require_password(args)?;
+    let out = args
+        .out
+        .as_deref()
+        .ok_or_else(|| "Missing required --out <file.csv>".to_string())?;
+    if !vault::verify_auth_password(root, password)?.ok {
+        return Err("Invalid password.".to_string());
+    }
+    let db = vault::load_db_value(root, password)?;
+    let rows = table_rows(&db, "todos");
+    write_csv(out, rows_to_csv_auto(rows.as_slice()).as_str())?;
+    println!("wrote {} rows to {}", rows.len(), out.display());
+    Ok(())
+}
+
+fn dump_weekly(args: &Args) -> Result<(), String> {
+    let root = require_data_dir(args)?;
+    let password = require_password(args)?;
+    let out = args
+        .out
+        .as_deref()
+        .ok_or_else(|| "Missing required --out <file.csv>".to_string())?;
+    if !vault::verify_auth_password(root, password)?.ok {
+        return Err("Invalid password.".to_string());
+    }
+    let db = vault::load_db_value(root, password)?;
+    let rows = weekly_rows(&db);
+    write_csv(out, rows_to_csv_auto(rows.as_slice()).as_str())?;
+    println!("wrote {} rows to {}", rows.len(), out.display());
+    Ok(())
+}
+
+/// Restores a full-vault backup produced by the app's export, replacing whatever is
+/// currently at `--data-dir`. Scripted automation wants a predictable outcome, not a
+/// merge, so (unlike the GUI's "append" import) this always replaces.
+fn apply_backup(args: &Args) -> Result<(), String> {
+    let root = require_data_dir(args)?;
+    let password = require_password(args)?;
+    let file = args
+        .file
+        .as_deref()
+        .ok_or_else(|| "Missing required --file <backup.json>".to_string())?;
+    if !vault::verify_auth_password(root, password)?.ok {
+        return Err("Invalid password.".to_string());
+    }
+    let raw = std::fs::read_to_string(file).map_err(|err| err.to_string())?;
+    let envelope: vault::CryptoEnvelope =
+        serde_json::from_str(raw.as_str()).map_err(|_| "Backup file is not valid JSON.".to_string())?;
+    let decrypted = vault::decrypt_envelope(&envelope, password)?
+        .ok_or_else(|| "Unable to decrypt the backup file.".to_string())?;
+    let value: serde_json::Value =
+        serde_json::from_str(decrypted.as_str()).map_err(|_| "Backup file is corrupt.".to_string())?;
+    let db = vault::ensure_db_shape_value(value);
+    vault::save_db_value(root, password, &db)?;
+    println!("restored vault from {}", file.display());
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, rest)) = raw.split_first() else {
+        return Err(
+            "Usage: vault-cli <unlock|list-tables|export-table|dump-todos|dump-weekly|apply-backup> [flags]"
+                .to_string(),
+        );
+    };
+    let args = parse_args(rest)?;
+    match command.as_str() {
+        "unlock" => unlock(&args),
+        "list-tables" => list_tables(&args),
+        "export-table" => export_table(&args),
+        "dump-todos" => dump_todos(&args),
+        "dump-weekly" => dump_weekly(&args),
+        "apply-backup" => apply_backup(&args),
+        other => Err(format!("Unknown command: {other}")),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Typed views over a handful of the JSON database's tables, used by the
+//! read-only aggregation paths (search, reporting, display rows) that used
+//! to walk `serde_json::Value` field-by-field with `value_string`/`value_i64`.
+//! The on-disk shape is unchanged -- `db["uniforms"]` is still a plain JSON
+//! array -- `uniform_entries` just converts it at the boundary instead of
+//! every call site re-deriving the same fields by hand. Mutation (upsert,
+//! decrement, crafting, recycle undo/redo) still operates on `db["uniforms"]`
+//! directly via `db_uniforms_mut` in main.rs, since those paths are threaded
+//! through the recipe/recycle machinery and aren't converted yet; this starts
+//! with the read side, the most duplicated of the two.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UniformEntry {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub alteration: String,
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub size: String,
+    #[serde(default)]
+    pub waist: String,
+    #[serde(default)]
+    pub inseam: String,
+    #[serde(default)]
+    pub quantity: i64,
+    #[serde(default)]
+    pub branch: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reorder_level: Option<i64>,
+    /// Free-form tags like `"low_stock"`, `"reserved"`, `"discontinued"` -- `"low_stock"` is
+    /// kept in sync automatically by `decrement_uniform_stock`/`upsert_uniform_stock`, the
+    /// rest are set by hand to mark stock that shouldn't be drawn from automatically.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+}
+
+/// Deserializes `db["uniforms"]` into typed rows, skipping any entry that
+/// doesn't parse (malformed rows are dropped from the read side the same way
+/// `value_string`/`value_i64` silently fell back to empty/zero before).
+pub fn uniform_entries(db: &serde_json::Value) -> Vec<UniformEntry> {
+    let Some(uniforms) = db.get("uniforms").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    uniforms
+        .iter()
+        .filter_map(|entry| serde_json::from_value(entry.clone()).ok())
+        .collect()
+}
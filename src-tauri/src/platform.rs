@@ -0,0 +1,70 @@
+//! Resolves the two things a mobile build changes relative to desktop: where the vault's
+//! files live, and how a caller-supplied `password` string proves the vault should unlock.
+//! `auth_setup`/`auth_verify`/`storage_root_dir` in `main.rs` go through `current()` instead
+//! of hard-coding desktop behavior, so the same invoke signatures (still a plain `password`
+//! field over the wire) work whether that string is what the user typed (desktop) or an
+//! opaque token handed back by a platform keystore/biometric prompt (Android) -- the
+//! frontend doesn't need a mobile-specific code path.
+
+use crate::vault::{self, AuthVerifyResult};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Per-platform storage location and key-unlock mechanism. Desktop and Android each implement
+/// this the same way the rest of the command layer already treats password verification: a
+/// root directory to read/write under, and a yes/no (plus rehash-on-success) verdict on a
+/// password string.
+pub trait VaultPlatform: Sync {
+    /// Base directory for app data, before the legacy-root migration heuristics in
+    /// `storage_root_dir` run.
+    fn app_data_dir(&self, app: &AppHandle) -> Result<PathBuf, String>;
+
+    /// Verifies `password` against the stored auth record and reports whether verifying also
+    /// triggered a transparent rehash (see `vault::verify_auth_password`).
+    fn verify(&self, root: &Path, password: &str) -> Result<AuthVerifyResult, String>;
+}
+
+/// Desktop: password is what the user typed, unlock is today's Argon2id/PBKDF2 verify-and-
+/// upgrade path in `vault::verify_auth_password`, data dir is Tauri's own `app_data_dir`.
+pub struct DesktopPlatform;
+
+impl VaultPlatform for DesktopPlatform {
+    fn app_data_dir(&self, app: &AppHandle) -> Result<PathBuf, String> {
+        app.path().app_data_dir().map_err(|err| err.to_string())
+    }
+
+    fn verify(&self, root: &Path, password: &str) -> Result<AuthVerifyResult, String> {
+        vault::verify_auth_password(root, password)
+    }
+}
+
+/// Android: the master key is unlocked behind the platform keystore and a biometric/PIN
+/// prompt, owned by the Kotlin side of a Tauri mobile plugin that doesn't live in this crate.
+/// By the time a `password` string reaches Rust here it's already the keystore-unwrapped
+/// secret the prompt released, not something the user typed -- so this impl's job is limited
+/// to what the command layer can see from Rust: the same stored-record verify/rehash path as
+/// desktop, with the data directory pointed at wherever `app_data_dir` resolves to on Android
+/// (the plugin is responsible for making sure that's backed by scoped storage, not this impl).
+#[cfg(target_os = "android")]
+pub struct AndroidPlatform;
+
+#[cfg(target_os = "android")]
+impl VaultPlatform for AndroidPlatform {
+    fn app_data_dir(&self, app: &AppHandle) -> Result<PathBuf, String> {
+        app.path().app_data_dir().map_err(|err| err.to_string())
+    }
+
+    fn verify(&self, root: &Path, password: &str) -> Result<AuthVerifyResult, String> {
+        vault::verify_auth_password(root, password)
+    }
+}
+
+#[cfg(target_os = "android")]
+pub fn current() -> &'static dyn VaultPlatform {
+    &AndroidPlatform
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn current() -> &'static dyn VaultPlatform {
+    &DesktopPlatform
+}
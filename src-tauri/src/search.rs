@@ -0,0 +1,253 @@
+//! Full-text search over kanban cards and candidate PII rows, backed by a
+//! tantivy index kept under the protected storage root. The index holds
+//! decrypted plaintext (names, IDs, notes) so it can be searched instantly,
+//! which is why it lives next to the encrypted vault files rather than
+//! anywhere world-readable, and why `reindex_all` wipes and rebuilds it
+//! whenever the underlying data is replaced wholesale (import `replace`, or
+//! to recover from corruption).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tantivy::collector::TopDocs;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, SchemaBuilder, Value as SchemaValue, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+const INDEX_DIRNAME: &str = "search_index";
+const INDEX_WRITER_BUDGET_BYTES: usize = 15_000_000;
+const SEARCH_RESULT_LIMIT: usize = 50;
+
+struct SearchFields {
+    candidate_uuid: Field,
+    candidate_name: Field,
+    icims_id: Field,
+    employee_id: Field,
+    req_id: Field,
+    job_name: Field,
+    job_location: Field,
+    manager: Field,
+    branch: Field,
+    notes: Field,
+}
+
+impl SearchFields {
+    fn text_fields(&self) -> Vec<Field> {
+        vec![
+            self.candidate_name,
+            self.icims_id,
+            self.employee_id,
+            self.req_id,
+            self.job_name,
+            self.job_location,
+            self.manager,
+            self.branch,
+            self.notes,
+        ]
+    }
+}
+
+fn build_schema() -> (Schema, SearchFields) {
+    let mut builder = SchemaBuilder::default();
+    let fields = SearchFields {
+        candidate_uuid: builder.add_text_field("candidate_uuid", STRING | STORED),
+        candidate_name: builder.add_text_field("candidate_name", TEXT),
+        icims_id: builder.add_text_field("icims_id", TEXT),
+        employee_id: builder.add_text_field("employee_id", TEXT),
+        req_id: builder.add_text_field("req_id", TEXT),
+        job_name: builder.add_text_field("job_name", TEXT),
+        job_location: builder.add_text_field("job_location", TEXT),
+        manager: builder.add_text_field("manager", TEXT),
+        branch: builder.add_text_field("branch", TEXT),
+        notes: builder.add_text_field("notes", TEXT),
+    };
+    (builder.build(), fields)
+}
+
+fn index_dir(root: &Path) -> PathBuf {
+    root.join(INDEX_DIRNAME)
+}
+
+fn open_or_create_index(root: &Path) -> Result<(Index, SearchFields), String> {
+    let (schema, fields) = build_schema();
+    let dir = index_dir(root);
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    let has_meta = dir.join("meta.json").exists();
+    let index = if has_meta {
+        Index::open_in_dir(&dir).map_err(|err| err.to_string())?
+    } else {
+        Index::create_in_dir(&dir, schema).map_err(|err| err.to_string())?
+    };
+    Ok((index, fields))
+}
+
+fn text_field(value: Option<&serde_json::Value>) -> String {
+    value.and_then(|v| v.as_str()).unwrap_or("").to_string()
+}
+
+struct CandidateDoc {
+    candidate_uuid: String,
+    candidate_name: String,
+    icims_id: String,
+    employee_id: String,
+    req_id: String,
+    job_name: String,
+    job_location: String,
+    manager: String,
+    branch: String,
+    notes: String,
+}
+
+fn candidate_doc(
+    candidate_uuid: &str,
+    card: Option<&serde_json::Value>,
+    row: Option<&serde_json::Value>,
+) -> CandidateDoc {
+    let notes = [
+        row.and_then(|r| r.get("Additional Details")),
+        row.and_then(|r| r.get("Additional Notes")),
+        row.and_then(|r| r.get("Emergency Contact Name")),
+        row.and_then(|r| r.get("Emergency Contact Relationship")),
+    ]
+    .iter()
+    .map(|v| text_field(*v))
+    .collect::<Vec<_>>()
+    .join(" ");
+    CandidateDoc {
+        candidate_uuid: candidate_uuid.to_string(),
+        candidate_name: text_field(card.and_then(|c| c.get("candidate_name"))),
+        icims_id: text_field(card.and_then(|c| c.get("icims_id"))),
+        employee_id: text_field(card.and_then(|c| c.get("employee_id"))),
+        req_id: text_field(card.and_then(|c| c.get("req_id"))),
+        job_name: text_field(card.and_then(|c| c.get("job_name"))),
+        job_location: text_field(card.and_then(|c| c.get("job_location"))),
+        manager: text_field(card.and_then(|c| c.get("manager"))),
+        branch: text_field(card.and_then(|c| c.get("branch"))),
+        notes,
+    }
+}
+
+fn add_document(writer: &mut IndexWriter, fields: &SearchFields, entry: &CandidateDoc) -> Result<(), String> {
+    writer
+        .add_document(doc!(
+            fields.candidate_uuid => entry.candidate_uuid.as_str(),
+            fields.candidate_name => entry.candidate_name.as_str(),
+            fields.icims_id => entry.icims_id.as_str(),
+            fields.employee_id => entry.employee_id.as_str(),
+            fields.req_id => entry.req_id.as_str(),
+            fields.job_name => entry.job_name.as_str(),
+            fields.job_location => entry.job_location.as_str(),
+            fields.manager => entry.manager.as_str(),
+            fields.branch => entry.branch.as_str(),
+            fields.notes => entry.notes.as_str(),
+        ))
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Deletes any existing document for `candidate_uuid` and re-adds it from the current
+/// card/row, so a single save keeps the index in sync with the decrypted data.
+pub fn upsert_candidate(
+    root: &Path,
+    candidate_uuid: &str,
+    card: Option<&serde_json::Value>,
+    row: Option<&serde_json::Value>,
+) -> Result<(), String> {
+    if candidate_uuid.is_empty() {
+        return Ok(());
+    }
+    let (index, fields) = open_or_create_index(root)?;
+    let mut writer: IndexWriter = index
+        .writer(INDEX_WRITER_BUDGET_BYTES)
+        .map_err(|err| err.to_string())?;
+    writer.delete_term(Term::from_field_text(fields.candidate_uuid, candidate_uuid));
+    add_document(&mut writer, &fields, &candidate_doc(candidate_uuid, card, row))?;
+    writer.commit().map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+pub fn delete_candidate(root: &Path, candidate_uuid: &str) -> Result<(), String> {
+    if candidate_uuid.is_empty() {
+        return Ok(());
+    }
+    let (index, fields) = open_or_create_index(root)?;
+    let mut writer: IndexWriter = index
+        .writer(INDEX_WRITER_BUDGET_BYTES)
+        .map_err(|err| err.to_string())?;
+    writer.delete_term(Term::from_field_text(fields.candidate_uuid, candidate_uuid));
+    writer.commit().map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Rebuilds the index from scratch from every kanban card + candidate row in `db`.
+/// Called after a full `replace` import (stale plaintext would otherwise linger) or to
+/// recover from a corrupt index, since the index is otherwise only ever patched
+/// incrementally by the per-field upserts above.
+pub fn reindex_all(root: &Path, db: &serde_json::Value) -> Result<(), String> {
+    let dir = index_dir(root);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|err| err.to_string())?;
+    }
+    let cards = db
+        .get("kanban")
+        .and_then(|v| v.get("cards"))
+        .and_then(|v| v.as_array());
+    let Some(cards) = cards else {
+        return Ok(());
+    };
+    let candidates = db
+        .get("kanban")
+        .and_then(|v| v.get("candidates"))
+        .and_then(|v| v.as_array());
+
+    let (index, fields) = open_or_create_index(root)?;
+    let mut writer: IndexWriter = index
+        .writer(INDEX_WRITER_BUDGET_BYTES)
+        .map_err(|err| err.to_string())?;
+    for card in cards {
+        let candidate_uuid = card.get("uuid").and_then(|v| v.as_str()).unwrap_or("");
+        if candidate_uuid.is_empty() {
+            continue;
+        }
+        let row = candidates.and_then(|rows| {
+            rows.iter()
+                .find(|row| row.get("candidate UUID").and_then(|v| v.as_str()) == Some(candidate_uuid))
+        });
+        add_document(&mut writer, &fields, &candidate_doc(candidate_uuid, Some(card), row))?;
+    }
+    writer.commit().map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Runs `query` across every searchable candidate field and returns matching
+/// `candidate_uuid`s ranked by score, highest first.
+pub fn search_candidates(root: &Path, query: &str) -> Result<Vec<String>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (index, fields) = open_or_create_index(root)?;
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::OnCommitWithDelay)
+        .try_into()
+        .map_err(|err: tantivy::TantivyError| err.to_string())?;
+    let searcher = reader.searcher();
+    let parser = QueryParser::for_index(&index, fields.text_fields());
+    let parsed = parser.parse_query(query).map_err(|err| err.to_string())?;
+    let top_docs = searcher
+        .search(&parsed, &TopDocs::with_limit(SEARCH_RESULT_LIMIT))
+        .map_err(|err| err.to_string())?;
+
+    let mut ranked: Vec<(String, f32)> = Vec::new();
+    for (score, doc_address) in top_docs {
+        let retrieved: TantivyDocument = searcher.doc(doc_address).map_err(|err| err.to_string())?;
+        if let Some(uuid) = retrieved
+            .get_first(fields.candidate_uuid)
+            .and_then(|value| value.as_str())
+        {
+            ranked.push((uuid.to_string(), score));
+        }
+    }
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(ranked.into_iter().map(|(uuid, _)| uuid).collect())
+}
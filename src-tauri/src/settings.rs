@@ -0,0 +1,136 @@
+//! Typed app-preference storage (theme, column layout, auto-lock timeout, etc.), kept
+//! separate from the vault's `db_*` commands and `meta.json` since these are per-install UI
+//! preferences rather than anything that needs encryption or travels with an exported/
+//! imported database. Settings are addressed by dot-notation path
+//! (e.g. `"ui.kanban.defaultColumn"`) against a single JSON file, deep-merged on read over a
+//! compile-time default so a key added to `default_settings` later always resolves even for a
+//! config file written by an older build.
+
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+fn default_settings() -> Value {
+    json!({
+        "ui": {
+            "theme": "system",
+            "kanban": {
+                "defaultColumn": "",
+            },
+        },
+        "security": {
+            "autoLockMinutes": 5,
+            "clipboardClearMs": 30_000,
+        },
+    })
+}
+
+fn settings_path(root: &Path) -> PathBuf {
+    root.join(SETTINGS_FILE)
+}
+
+/// Deep-merges `incoming` over `base` object-by-object -- any key `incoming` doesn't set
+/// falls back to `base`'s value, and any non-object value in `incoming` wins outright.
+fn deep_merge(base: &Value, incoming: &Value) -> Value {
+    match (base, incoming) {
+        (Value::Object(base_obj), Value::Object(incoming_obj)) => {
+            let mut merged = base_obj.clone();
+            for (key, incoming_value) in incoming_obj {
+                let merged_value = match merged.get(key) {
+                    Some(base_value) => deep_merge(base_value, incoming_value),
+                    None => incoming_value.clone(),
+                };
+                merged.insert(key.clone(), merged_value);
+            }
+            Value::Object(merged)
+        }
+        (_, incoming) => incoming.clone(),
+    }
+}
+
+fn load_raw(root: &Path) -> Value {
+    let Ok(bytes) = fs::read(settings_path(root)) else {
+        return json!({});
+    };
+    serde_json::from_slice(bytes.as_slice()).unwrap_or(json!({}))
+}
+
+/// The full settings document: whatever is on disk, deep-merged over the compile-time default.
+pub fn load(root: &Path) -> Value {
+    deep_merge(&default_settings(), &load_raw(root))
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path.split('.').filter(|segment| !segment.is_empty()).collect()
+}
+
+fn get_path<'a>(value: &'a Value, segments: &[&str]) -> Option<&'a Value> {
+    let mut current = value;
+    for segment in segments {
+        current = current.as_object()?.get(*segment)?;
+    }
+    Some(current)
+}
+
+/// Looks up `path` against the merged (on-disk-over-defaults) document. Returns `Value::Null`
+/// for a path that resolves nowhere, same as `Value::get` would for a missing key.
+pub fn get(root: &Path, path: &str) -> Value {
+    let segments = split_path(path);
+    if segments.is_empty() {
+        return load(root);
+    }
+    get_path(&load(root), segments.as_slice())
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+/// Whether `path` resolves to a value in the merged document (i.e. either set on disk or
+/// covered by a default), not merely whether it's non-null.
+pub fn has(root: &Path, path: &str) -> bool {
+    let segments = split_path(path);
+    if segments.is_empty() {
+        return true;
+    }
+    get_path(&load(root), segments.as_slice()).is_some()
+}
+
+fn set_path(node: &mut Value, segments: &[&str], value: Value) {
+    if !node.is_object() {
+        *node = json!({});
+    }
+    let obj = node.as_object_mut().expect("node was just made an object");
+    if segments.len() == 1 {
+        obj.insert(segments[0].to_string(), value);
+        return;
+    }
+    let child = obj
+        .entry(segments[0].to_string())
+        .or_insert_with(|| json!({}));
+    set_path(child, &segments[1..], value);
+}
+
+/// Sets `path` to `value` on the on-disk document (not the merged view -- writing a value
+/// equal to its default is still recorded, same as any other edit), creating intermediate
+/// objects as needed, then atomically rewrites the file (write temp + rename).
+pub fn set(root: &Path, path: &str, value: Value) -> Result<(), String> {
+    let segments = split_path(path);
+    if segments.is_empty() {
+        return Err("Settings path is required.".to_string());
+    }
+    let mut doc = load_raw(root);
+    set_path(&mut doc, segments.as_slice(), value);
+    write(root, &doc)
+}
+
+fn write(root: &Path, value: &Value) -> Result<(), String> {
+    let path = settings_path(root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(value).map_err(|err| err.to_string())?;
+    let tmp = path.with_extension("settings-tmp");
+    fs::write(tmp.as_path(), content.as_bytes()).map_err(|err| err.to_string())?;
+    fs::rename(tmp.as_path(), path.as_path()).map_err(|err| err.to_string())
+}
@@ -0,0 +1,272 @@
+//! A SQLite-backed mirror of the vault tables, used purely for paginated
+//! reads and the hot kanban-card-edit path.
+//!
+//! `vault::load_db_value`/`save_db_value` remain the source of truth for
+//! every mutation: the JSON checkpoint + op log is what gets imported, merged
+//! and rotated elsewhere in this crate. This module exists because
+//! `db_get_table`/`db_list_tables` used to decrypt and walk that entire JSON
+//! value just to hand back one page of one table, which stops scaling once
+//! candidates/cards run into the thousands. Instead we keep a row-per-record
+//! SQLite file under the storage root (itself wrapped in the same
+//! `CryptoEnvelope` on flush, decrypted back to a temp file on open, so
+//! nothing plaintext is left on disk between calls), rebuild it from the JSON
+//! snapshot whenever the two have drifted, and query it with real
+//! LIMIT/OFFSET. Those commands still load the full JSON value and build
+//! every table's rows on each call to detect drift (`sync_if_stale` takes
+//! them as input); only the page and count reads come from SQLite.
+//! `db_kanban_update_card` additionally issues a targeted
+//! `INSERT OR REPLACE` against this store instead of waiting for the next
+//! full rebuild, so a single card edit stays cheap.
+//!
+//! sqlx is async; the rest of this crate's commands are not, so each public
+//! function here opens its own pool, does its work, flushes, and returns --
+//! bridged onto a small lazily-started current-thread Tokio runtime rather
+//! than threading `async fn` through every Tauri command.
+
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Row, SqlitePool};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::vault::{decode_b64, decrypt_envelope, encode_b64, encrypt_text, CryptoEnvelope, DB_TABLE_ORDER};
+
+const SQLITE_ENC_FILE: &str = "tables.sqlite.enc";
+const SQLITE_PLAIN_FILE: &str = "tables.sqlite.tmp";
+const FINGERPRINT_TABLE: &str = "_sync_state";
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RT: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RT.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start sqlite runtime")
+    })
+}
+
+fn enc_path(root: &Path) -> PathBuf {
+    root.join(SQLITE_ENC_FILE)
+}
+
+fn plain_path(root: &Path) -> PathBuf {
+    root.join(SQLITE_PLAIN_FILE)
+}
+
+fn db_fingerprint(db: &serde_json::Value) -> String {
+    let serialized = serde_json::to_vec(db).unwrap_or_default();
+    let digest = Sha256::digest(&serialized);
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decrypts the sqlite file (if one exists yet) to a plaintext temp file and opens a pool
+/// against it. Callers must pair this with `seal` once they're done with the pool.
+async fn open_pool(root: &Path, password: &str) -> Result<SqlitePool, String> {
+    let plain = plain_path(root);
+    let encrypted = enc_path(root);
+    if encrypted.exists() {
+        let raw = fs::read_to_string(encrypted.as_path()).map_err(|err| err.to_string())?;
+        let envelope: CryptoEnvelope = serde_json::from_str(raw.as_str())
+            .map_err(|_| "Search table store is corrupt.".to_string())?;
+        let decoded = decrypt_envelope(&envelope, password)?
+            .ok_or_else(|| "Invalid password.".to_string())?;
+        let bytes = decode_b64(decoded.as_str())?;
+        fs::write(plain.as_path(), bytes).map_err(|err| err.to_string())?;
+    } else if plain.exists() {
+        let _ = fs::remove_file(plain.as_path());
+    }
+
+    let options = SqliteConnectOptions::from_str(&format!("sqlite://{}", plain.display()))
+        .map_err(|err| err.to_string())?
+        .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(|err| err.to_string())?;
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .map_err(|err| err.to_string())?;
+    sqlx::query(&format!(
+        "CREATE TABLE IF NOT EXISTS {FINGERPRINT_TABLE} (id INTEGER PRIMARY KEY CHECK (id = 0), fingerprint TEXT NOT NULL)"
+    ))
+    .execute(&pool)
+    .await
+    .map_err(|err| err.to_string())?;
+    Ok(pool)
+}
+
+/// Closes the pool, re-encrypts the plaintext file under `password`, and removes the
+/// plaintext copy so nothing decrypted lingers on disk once a command returns.
+async fn seal_pool(pool: SqlitePool, root: &Path, password: &str) -> Result<(), String> {
+    pool.close().await;
+    let plain = plain_path(root);
+    let bytes = fs::read(plain.as_path()).map_err(|err| err.to_string())?;
+    let encoded = encode_b64(bytes.as_slice());
+    let envelope = encrypt_text(encoded.as_str(), password)?;
+    let content = serde_json::to_string(&envelope).map_err(|err| err.to_string())?;
+    fs::write(enc_path(root), content).map_err(|err| err.to_string())?;
+    let _ = fs::remove_file(plain.as_path());
+    Ok(())
+}
+
+async fn read_fingerprint(pool: &SqlitePool) -> Option<String> {
+    sqlx::query(&format!("SELECT fingerprint FROM {FINGERPRINT_TABLE} WHERE id = 0"))
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|row| row.try_get::<String, _>("fingerprint").ok())
+}
+
+async fn write_fingerprint(pool: &SqlitePool, fingerprint: &str) -> Result<(), String> {
+    sqlx::query(&format!(
+        "INSERT INTO {FINGERPRINT_TABLE} (id, fingerprint) VALUES (0, ?1)
+         ON CONFLICT(id) DO UPDATE SET fingerprint = excluded.fingerprint"
+    ))
+    .bind(fingerprint)
+    .execute(pool)
+    .await
+    .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+async fn rebuild_table(
+    pool: &SqlitePool,
+    table_id: &str,
+    rows: &[serde_json::Value],
+) -> Result<(), String> {
+    sqlx::query(&format!("DELETE FROM {table_id}"))
+        .execute(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+    for (idx, row) in rows.iter().enumerate() {
+        let row_id = row
+            .get("__rowId")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let data = serde_json::to_string(row).map_err(|err| err.to_string())?;
+        sqlx::query(&format!(
+            "INSERT INTO {table_id} (row_id, sort_order, data) VALUES (?1, ?2, ?3)"
+        ))
+        .bind(row_id)
+        .bind(idx as i64)
+        .bind(data)
+        .execute(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// Rebuilds every mirrored table from `tables` (one `(table_id, rows)` pair per
+/// `DB_TABLE_ORDER` entry, each row already shaped the way `db_get_table` returns it) if the
+/// JSON snapshot's fingerprint has changed since the last sync -- covering the one-time
+/// import on first open as well as every later drift (new card, import replace/append, undo).
+pub fn sync_if_stale(
+    root: &Path,
+    password: &str,
+    db: &serde_json::Value,
+    tables: &[(&'static str, Vec<serde_json::Value>)],
+) -> Result<(), String> {
+    let fingerprint = db_fingerprint(db);
+    runtime().block_on(async {
+        let pool = open_pool(root, password).await?;
+        let current = read_fingerprint(&pool).await;
+        if current.as_deref() != Some(fingerprint.as_str()) {
+            for table_id in DB_TABLE_ORDER {
+                let rows = tables
+                    .iter()
+                    .find(|(id, _)| *id == table_id)
+                    .map(|(_, rows)| rows.as_slice())
+                    .unwrap_or(&[]);
+                rebuild_table(&pool, table_id, rows).await?;
+            }
+            write_fingerprint(&pool, fingerprint.as_str()).await?;
+        }
+        seal_pool(pool, root, password).await
+    })
+}
+
+/// Returns `(total_row_count, page)` for `table_id`, reading only the requested slice.
+pub fn page_table(
+    root: &Path,
+    password: &str,
+    table_id: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<(usize, Vec<serde_json::Value>), String> {
+    runtime().block_on(async {
+        let pool = open_pool(root, password).await?;
+        let total: i64 = sqlx::query(&format!("SELECT COUNT(*) AS n FROM {table_id}"))
+            .fetch_one(&pool)
+            .await
+            .map_err(|err| err.to_string())?
+            .try_get("n")
+            .map_err(|err| err.to_string())?;
+        let rows = sqlx::query(&format!(
+            "SELECT data FROM {table_id} ORDER BY sort_order ASC LIMIT ?1 OFFSET ?2"
+        ))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&pool)
+        .await
+        .map_err(|err| err.to_string())?;
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let data: String = row.try_get("data").map_err(|err| err.to_string())?;
+            out.push(serde_json::from_str(data.as_str()).unwrap_or(serde_json::Value::Null));
+        }
+        seal_pool(pool, root, password).await?;
+        Ok((total.max(0) as usize, out))
+    })
+}
+
+/// Returns the row count for `table_id` without materializing any rows.
+pub fn count_table(root: &Path, password: &str, table_id: &str) -> Result<usize, String> {
+    runtime().block_on(async {
+        let pool = open_pool(root, password).await?;
+        let total: i64 = sqlx::query(&format!("SELECT COUNT(*) AS n FROM {table_id}"))
+            .fetch_one(&pool)
+            .await
+            .map_err(|err| err.to_string())?
+            .try_get("n")
+            .map_err(|err| err.to_string())?;
+        seal_pool(pool, root, password).await?;
+        Ok(total.max(0) as usize)
+    })
+}
+
+/// Issues a targeted `INSERT OR REPLACE` for a single row instead of waiting for the next
+/// full `sync_if_stale` rebuild -- used by `db_kanban_update_card` since card edits are the
+/// hottest write path.
+pub fn upsert_row(
+    root: &Path,
+    password: &str,
+    table_id: &str,
+    row_id: &str,
+    sort_order: i64,
+    row: &serde_json::Value,
+) -> Result<(), String> {
+    if row_id.is_empty() {
+        return Ok(());
+    }
+    let data = serde_json::to_string(row).map_err(|err| err.to_string())?;
+    runtime().block_on(async {
+        let pool = open_pool(root, password).await?;
+        sqlx::query(&format!(
+            "INSERT INTO {table_id} (row_id, sort_order, data) VALUES (?1, ?2, ?3)
+             ON CONFLICT(row_id) DO UPDATE SET sort_order = excluded.sort_order, data = excluded.data"
+        ))
+        .bind(row_id)
+        .bind(sort_order)
+        .bind(data)
+        .execute(&pool)
+        .await
+        .map_err(|err| err.to_string())?;
+        seal_pool(pool, root, password).await
+    })
+}